@@ -45,12 +45,67 @@ use pest::Parser;
 mod parser;
 use self::parser::{BacktraceParser, Rule};
 
+mod reader;
+pub use self::reader::{BacktraceReader, OwnedFrame, OwnedSymbol};
+
 #[derive(Debug)]
 /// Represents a parser error.
 pub struct Error<'a> {
     inner: pest::Error<'a, Rule>,
 }
 
+impl<'a> Error<'a> {
+    /// Materialize this error into an owned [`ParseError`] that no longer
+    /// borrows the input.
+    ///
+    /// This is useful when the error needs to outlive the input string, for
+    /// example to stash it in a `failure::Error`, send it across threads, or
+    /// return it from a function that owns its buffer.
+    pub fn to_owned(&self) -> ParseError {
+        let message = self.inner.to_string();
+        match self.inner {
+            pest::Error::ParsingError {
+                ref positives,
+                ref negatives,
+                ref pos,
+            } => {
+                let (line, column) = pos.line_col();
+                let mut expected = Vec::with_capacity(positives.len() + negatives.len());
+                expected.extend(positives.iter().map(|rule| format!("{:?}", rule)));
+                expected.extend(negatives.iter().map(|rule| format!("!{:?}", rule)));
+                ParseError {
+                    offset: pos.pos(),
+                    line,
+                    column,
+                    message,
+                    expected,
+                }
+            }
+            pest::Error::CustomErrorPos { ref pos, .. } => {
+                let (line, column) = pos.line_col();
+                ParseError {
+                    offset: pos.pos(),
+                    line,
+                    column,
+                    message,
+                    expected: Vec::new(),
+                }
+            }
+            pest::Error::CustomErrorSpan { ref span, .. } => {
+                let pos = span.start_pos();
+                let (line, column) = pos.line_col();
+                ParseError {
+                    offset: pos.pos(),
+                    line,
+                    column,
+                    message,
+                    expected: Vec::new(),
+                }
+            }
+        }
+    }
+}
+
 impl<'a> fmt::Display for Error<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Display::fmt(&self.inner, f)
@@ -59,34 +114,298 @@ impl<'a> fmt::Display for Error<'a> {
 
 impl<'a> error::Error for Error<'a> {}
 
+#[derive(Clone, Debug)]
+/// Represents an owned parse error that does not borrow the input.
+///
+/// Unlike [`Error`], this type carries no lifetime and can therefore be stored,
+/// sent across threads, or returned from a function that owns its input buffer.
+/// Obtain one via [`Error::to_owned`] or [`Backtrace::parse_owned`].
+pub struct ParseError {
+    offset: usize,
+    line: usize,
+    column: usize,
+    message: String,
+    expected: Vec<String>,
+}
+
+impl ParseError {
+    /// Return the byte offset at which parsing failed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Return the 1-based line at which parsing failed.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the 1-based column at which parsing failed.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Return a human-readable description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return the set of tokens that would have been accepted at the point of
+    /// failure. Negated (unexpected) rules are prefixed with `!`.
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.message, f)
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Debug)]
+/// Represents a frame that could not be parsed while recovering from an error.
+///
+/// Produced by [`Backtrace::parse_lossy`] for each region of the input that did
+/// not parse as a frame. The offending text is retained by reference, so the
+/// caller can surface it in a warning.
+pub struct FrameError<'a> {
+    byte_offset: usize,
+    line: usize,
+    snippet: &'a str,
+}
+
+impl<'a> FrameError<'a> {
+    /// Return the byte offset of the malformed frame within the input.
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    /// Return the 1-based line number of the malformed frame's header.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the text that was skipped while recovering.
+    pub fn snippet(&self) -> &'a str {
+        self.snippet
+    }
+}
+
+impl<'a> fmt::Display for FrameError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "malformed frame at line {}: {}", self.line, self.snippet)
+    }
+}
+
+impl<'a> error::Error for FrameError<'a> {}
+
 #[derive(Debug)]
 /// Represents a parsed backtrace.
 pub struct Backtrace<'a> {
-    pairs: pest::iterators::Pairs<'a, Rule>,
+    frames: Vec<pest::iterators::Pair<'a, Rule>>,
 }
 
 impl<'a> Backtrace<'a> {
     /// Parse the provided input string and return either a parsed backtrace,
     /// or a parse error.
     pub fn parse(input: &'a str) -> Result<Backtrace<'a>, Error<'a>> {
-        let pairs =
-            BacktraceParser::parse(Rule::backtrace, input).map_err(|err| Error { inner: err })?;
+        let rule = match Dialect::detect(input) {
+            Dialect::Legacy => Rule::backtrace,
+            Dialect::Std => Rule::backtrace_std,
+        };
+        let pairs = BacktraceParser::parse(rule, input).map_err(|err| Error { inner: err })?;
+
+        Ok(Backtrace {
+            frames: collect_frames(pairs),
+        })
+    }
 
-        Ok(Backtrace { pairs })
+    /// Parse the provided input string, returning an owned [`ParseError`] on
+    /// failure.
+    ///
+    /// This behaves exactly like [`parse`](Backtrace::parse) on success — the
+    /// returned backtrace still borrows the input for zero-copy access — but
+    /// the error variant no longer borrows it and can outlive the input.
+    pub fn parse_owned(input: &'a str) -> Result<Backtrace<'a>, ParseError> {
+        Backtrace::parse(input).map_err(|err| err.to_owned())
+    }
+
+    /// Parse the provided input string, recovering from malformed frames.
+    ///
+    /// Rather than failing the whole input when a single frame does not parse
+    /// — as [`parse`](Backtrace::parse) does — this resynchronizes to the next
+    /// frame header of the detected dialect (`\d+: 0x...` for the legacy layout,
+    /// `\d+: symbol` for the libstd one) and resumes. The returned backtrace contains
+    /// every frame that did parse, and the returned vector describes each frame
+    /// that was skipped, so the caller can decide whether to warn or bail.
+    pub fn parse_lossy(input: &'a str) -> (Backtrace<'a>, Vec<FrameError<'a>>) {
+        let dialect = Dialect::detect(input);
+        let mut frames = Vec::new();
+        let mut errors = Vec::new();
+
+        for segment in frame_segments(input, dialect) {
+            match BacktraceParser::parse(dialect.frame_rule(), segment.text.trim_end()) {
+                Ok(pairs) => frames.extend(collect_frames(pairs)),
+                Err(_) => errors.push(FrameError {
+                    byte_offset: segment.byte_offset,
+                    line: segment.line,
+                    snippet: segment.text,
+                }),
+            }
+        }
+
+        (Backtrace { frames }, errors)
     }
 
     /// Create an iterator over the stack frames in this backtrace.
     pub fn frames(&self) -> Frames<'a> {
         Frames {
-            inner: self.pairs.clone(),
+            inner: self.frames.clone().into_iter(),
+        }
+    }
+}
+
+/// The backtrace layout a particular input is written in.
+///
+/// The crate originally only understood the `idx: 0xptr - symbol` layout
+/// emitted by the [`backtrace`][1] crate. Current `RUST_BACKTRACE=1` output from
+/// libstd uses a different layout — `   3: core::panicking::panic_fmt` with no
+/// pointer and an indented `at /rustc/<hash>/.../file.rs:123:45` location line
+/// carrying a column — so [`parse`](Backtrace::parse) sniffs the input and
+/// dispatches to the matching sub-grammar.
+///
+/// [1]: https://crates.io/crates/backtrace
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Dialect {
+    Legacy,
+    Std,
+}
+
+impl Dialect {
+    /// Sniff the dialect of `input`, falling back to the legacy layout when
+    /// nothing recognizable appears.
+    pub(crate) fn detect(input: &str) -> Dialect {
+        Dialect::try_detect(input).unwrap_or(Dialect::Legacy)
+    }
+
+    /// Sniff the dialect of `input`, returning `None` while no frame has yet
+    /// been seen in a trustworthy context.
+    ///
+    /// A legacy header is unambiguous (it carries a `0x` pointer), so it is
+    /// accepted anywhere. A libstd header (`\d+: symbol`, no pointer) is only
+    /// trusted inside a real frame context — after the `stack backtrace:`
+    /// marker, or immediately followed by an `at …` location line — so a stray
+    /// log line such as `12: connecting to host` does not misclassify an
+    /// otherwise-legacy backtrace.
+    pub(crate) fn try_detect(input: &str) -> Option<Dialect> {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut seen_marker = false;
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim_start().starts_with("stack backtrace:") {
+                seen_marker = true;
+                continue;
+            }
+            if reader::is_header(line) {
+                return Some(Dialect::Legacy);
+            }
+            if is_std_header(line) {
+                let followed_by_location = lines
+                    .get(index + 1)
+                    .map_or(false, |next| next.trim_start().starts_with("at "));
+                if seen_marker || followed_by_location {
+                    return Some(Dialect::Std);
+                }
+            }
+        }
+        None
+    }
+
+    /// Test whether `line` is a frame header for this dialect.
+    pub(crate) fn is_header(self, line: &str) -> bool {
+        match self {
+            Dialect::Legacy => reader::is_header(line),
+            Dialect::Std => is_std_header(line),
+        }
+    }
+
+    /// Return the grammar rule matching a single frame in this dialect.
+    pub(crate) fn frame_rule(self) -> Rule {
+        match self {
+            Dialect::Legacy => Rule::frame,
+            Dialect::Std => Rule::frame_std,
+        }
+    }
+}
+
+/// Test whether `line` is a libstd-style frame header: optional leading
+/// whitespace, a decimal index, `: `, and a symbol name (but no `0x` pointer).
+fn is_std_header(line: &str) -> bool {
+    let line = line.trim_start();
+    let index_end = line.find(|c: char| !c.is_digit(10)).unwrap_or(line.len());
+    if index_end == 0 {
+        return false;
+    }
+    let rest = line[index_end..].trim_start_matches(':');
+    if rest.len() == line[index_end..].len() {
+        return false;
+    }
+    let rest = rest.trim_start();
+    !rest.is_empty() && !rest.starts_with("0x")
+}
+
+/// Collect the `frame` pairs reachable from `pairs`, regardless of how the
+/// grammar nests them under the top-level `backtrace` rule.
+fn collect_frames<'a>(
+    pairs: pest::iterators::Pairs<'a, Rule>,
+) -> Vec<pest::iterators::Pair<'a, Rule>> {
+    let mut frames = Vec::new();
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::frame | Rule::frame_std => frames.push(pair),
+            _ => frames.extend(collect_frames(pair.into_inner())),
+        }
+    }
+    frames
+}
+
+/// A slice of the input beginning at a frame header, together with its
+/// position, as used by [`Backtrace::parse_lossy`].
+struct Segment<'a> {
+    byte_offset: usize,
+    line: usize,
+    text: &'a str,
+}
+
+/// Split `input` into segments, each starting at a frame header line for the
+/// given dialect. Any preamble before the first header (such as
+/// `stack backtrace:`) is dropped.
+fn frame_segments(input: &str, dialect: Dialect) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut offset = 0;
+    let mut lineno = 0;
+    for line in input.split_inclusive('\n') {
+        lineno += 1;
+        if dialect.is_header(line) {
+            if let Some(last) = segments.last_mut() {
+                last.text = &input[last.byte_offset..offset];
+            }
+            segments.push(Segment {
+                byte_offset: offset,
+                line: lineno,
+                text: &input[offset..],
+            });
         }
+        offset += line.len();
     }
+    segments
 }
 
 #[derive(Debug)]
 /// Iterator over the stack frames in a parsed backtrace.
 pub struct Frames<'a> {
-    inner: pest::iterators::Pairs<'a, Rule>,
+    inner: std::vec::IntoIter<pest::iterators::Pair<'a, Rule>>,
 }
 
 impl<'a> Iterator for Frames<'a> {
@@ -94,15 +413,38 @@ impl<'a> Iterator for Frames<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(frame) = self.inner.next() {
-            debug_assert!(frame.as_rule() == Rule::frame);
+            debug_assert!(frame.as_rule() == Rule::frame || frame.as_rule() == Rule::frame_std);
             let mut frame_inner = frame.into_inner();
 
             let frame_index = frame_inner.next().unwrap();
             debug_assert!(frame_index.as_rule() == Rule::frame_index);
-            let frame_pointer = frame_inner.next().unwrap();
-            debug_assert!(frame_pointer.as_rule() == Rule::frame_pointer);
+            let index = frame_index
+                .into_span()
+                .as_str()
+                .trim_end_matches(':')
+                .trim()
+                .parse()
+                .unwrap_or(0);
+
+            // The legacy dialect carries an instruction pointer; the libstd
+            // dialect does not, so it defaults to zero when absent.
+            let address = if frame_inner
+                .clone()
+                .next()
+                .map_or(false, |pair| pair.as_rule() == Rule::frame_pointer)
+            {
+                let frame_pointer = frame_inner.next().unwrap();
+                let digits = frame_pointer.into_span().as_str().trim_start_matches("0x");
+                u64::from_str_radix(digits, 16).unwrap_or(0)
+            } else {
+                0
+            };
 
-            Some(Frame { pairs: frame_inner })
+            Some(Frame {
+                index,
+                address,
+                pairs: frame_inner,
+            })
         } else {
             None
         }
@@ -112,10 +454,25 @@ impl<'a> Iterator for Frames<'a> {
 #[derive(Debug)]
 /// Represents a parsed stack frame.
 pub struct Frame<'a> {
+    index: u64,
+    address: u64,
     pairs: pest::iterators::Pairs<'a, Rule>,
 }
 
 impl<'a> Frame<'a> {
+    /// Return the position of this frame within the backtrace.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Return the instruction pointer of this frame.
+    ///
+    /// This is zero for dialects that do not record an address, such as the
+    /// libstd backtrace format.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
     /// Create an iterator over the symbols in this stack frame.
     pub fn symbols(&self) -> Symbols<'a> {
         Symbols {
@@ -141,6 +498,7 @@ impl<'a> Iterator for Symbols<'a> {
                         name: None,
                         filename: None,
                         lineno: None,
+                        column: None,
                     };
                     let mut symbol_inner = symbol.into_inner();
                     let symbol_name = symbol_inner.next().unwrap();
@@ -163,6 +521,13 @@ impl<'a> Iterator for Symbols<'a> {
                         );
                         parsed_symbol.lineno =
                             symbol_location_lineno.into_span().as_str().parse().ok();
+                        if let Some(symbol_location_column) = symbol_location_inner.next() {
+                            debug_assert!(
+                                symbol_location_column.as_rule() == Rule::symbol_location_column
+                            );
+                            parsed_symbol.column =
+                                symbol_location_column.into_span().as_str().parse().ok();
+                        }
                     }
                     Some(parsed_symbol)
                 }
@@ -180,6 +545,7 @@ pub struct Symbol<'a> {
     name: Option<&'a str>,
     filename: Option<&'a Path>,
     lineno: Option<u32>,
+    column: Option<u32>,
 }
 
 impl<'a> Symbol<'a> {
@@ -197,4 +563,12 @@ impl<'a> Symbol<'a> {
     pub fn lineno(&self) -> Option<u32> {
         self.lineno
     }
+
+    /// Return the column in source file, if known.
+    ///
+    /// This is only populated by the libstd backtrace dialect; the legacy
+    /// dialect does not record a column, so it is always `None` there.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
 }
@@ -0,0 +1,309 @@
+//! Incremental, streaming parsing from a `BufRead` source.
+//!
+//! Unlike [`Backtrace::parse`](::Backtrace::parse), which needs the whole
+//! backtrace available as a single `&str` up front, [`BacktraceReader`] pulls
+//! bytes on demand and yields one frame at a time. This is convenient when a
+//! backtrace is arriving line-by-line, for instance from a child process's
+//! stderr or the tail of a log file, and keeps memory bounded for very large
+//! backtraces.
+//!
+//! Because the frames are produced independently of the input, they are owned
+//! (`String`/`PathBuf`/`u32`) rather than borrowed.
+
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+use pest::Parser;
+
+use parser::{BacktraceParser, Rule};
+use Dialect;
+
+/// An owned, input-independent symbol, as produced by [`BacktraceReader`].
+#[derive(Clone, Debug)]
+pub struct OwnedSymbol {
+    name: Option<String>,
+    filename: Option<PathBuf>,
+    lineno: Option<u32>,
+    column: Option<u32>,
+}
+
+impl OwnedSymbol {
+    /// Return the name of the symbol, if resolved.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Return the path of the source file, if known.
+    pub fn filename(&self) -> Option<&Path> {
+        self.filename.as_ref().map(PathBuf::as_path)
+    }
+
+    /// Return the line number in source file, if known.
+    pub fn lineno(&self) -> Option<u32> {
+        self.lineno
+    }
+
+    /// Return the column in source file, if known.
+    ///
+    /// As with [`Symbol::column`](::Symbol::column), this is only populated by
+    /// the libstd backtrace dialect.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+}
+
+/// An owned stack frame, as produced by [`BacktraceReader`].
+#[derive(Clone, Debug)]
+pub struct OwnedFrame {
+    index: u64,
+    address: u64,
+    symbols: Vec<OwnedSymbol>,
+}
+
+impl OwnedFrame {
+    /// Return the position of this frame within the backtrace.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Return the instruction pointer of this frame.
+    ///
+    /// As with [`Frame::address`](::Frame::address), this is zero for dialects
+    /// that do not record an address, such as the libstd backtrace format.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Return the symbols resolved for this stack frame.
+    pub fn symbols(&self) -> &[OwnedSymbol] {
+        &self.symbols
+    }
+}
+
+/// A streaming parser that reads a backtrace from a [`BufRead`] source and
+/// yields owned frames as they become available.
+///
+/// Frames are recognized incrementally: a frame is only considered complete
+/// once the header of the following frame or the end of the input has been
+/// observed, because a frame's trailing `at path:line` lines belong to it. Both
+/// the legacy and the libstd dialect are accepted; the dialect is detected from
+/// the first frame and then used for the remainder of the input. The last frame
+/// is flushed on EOF.
+#[derive(Debug)]
+pub struct BacktraceReader<R> {
+    reader: R,
+    buffer: String,
+    dialect: Option<Dialect>,
+    eof: bool,
+}
+
+impl<R: BufRead> BacktraceReader<R> {
+    /// Create a new reader pulling from the provided [`BufRead`] source.
+    pub fn new(reader: R) -> BacktraceReader<R> {
+        BacktraceReader {
+            reader,
+            buffer: String::new(),
+            dialect: None,
+            eof: false,
+        }
+    }
+
+    /// Read and return the next completed frame, or `None` once the input is
+    /// exhausted.
+    ///
+    /// Returns an error if reading from the underlying source fails.
+    pub fn next_frame(&mut self) -> io::Result<Option<OwnedFrame>> {
+        loop {
+            // Settle on a dialect from the buffered input before recognizing
+            // any frames, so pointer-less libstd frames are handled too.
+            let dialect = match self.dialect {
+                Some(dialect) => dialect,
+                None => match Dialect::try_detect(&self.buffer) {
+                    Some(dialect) => {
+                        self.dialect = Some(dialect);
+                        dialect
+                    }
+                    None if self.eof => {
+                        self.buffer.clear();
+                        return Ok(None);
+                    }
+                    None => {
+                        self.fill()?;
+                        continue;
+                    }
+                },
+            };
+
+            // Drop any preamble (such as the `stack backtrace:` header) that
+            // precedes the first frame header.
+            match next_header(&self.buffer, dialect) {
+                Some(start) => {
+                    if start > 0 {
+                        self.buffer.drain(..start);
+                    }
+                }
+                None if self.eof => {
+                    self.buffer.clear();
+                    return Ok(None);
+                }
+                None => {
+                    self.fill()?;
+                    continue;
+                }
+            }
+
+            // The current frame extends up to the next frame header. Scan from
+            // the end of the current header line so it cannot re-match itself.
+            let header_len = first_line_len(&self.buffer);
+            match next_header(&self.buffer[header_len..], dialect).map(|offset| offset + header_len)
+            {
+                Some(end) => {
+                    let frame = parse_frame(&self.buffer[..end], dialect);
+                    self.buffer.drain(..end);
+                    if let Some(frame) = frame {
+                        return Ok(Some(frame));
+                    }
+                }
+                None if self.eof => {
+                    let frame = parse_frame(&self.buffer, dialect);
+                    self.buffer.clear();
+                    return Ok(frame);
+                }
+                None => {
+                    self.fill()?;
+                }
+            }
+        }
+    }
+
+    /// Pull one more line into the buffer, recording EOF.
+    fn fill(&mut self) -> io::Result<()> {
+        if self.reader.read_line(&mut self.buffer)? == 0 {
+            self.eof = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for BacktraceReader<R> {
+    type Item = io::Result<OwnedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_frame() {
+            Ok(Some(frame)) => Some(Ok(frame)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Return the byte length of the first line of `input`, including its trailing
+/// newline if present.
+fn first_line_len(input: &str) -> usize {
+    match input.find('\n') {
+        Some(index) => index + 1,
+        None => input.len(),
+    }
+}
+
+/// Find the byte offset of the next line matching a frame header for the given
+/// dialect in `input`, if any.
+fn next_header(input: &str, dialect: Dialect) -> Option<usize> {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if dialect.is_header(line) {
+            return Some(offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Test whether `line` starts with a frame header: optional leading
+/// whitespace, a decimal index, `: `, and a `0x` hex pointer.
+pub(crate) fn is_header(line: &str) -> bool {
+    let line = line.trim_start();
+    let index_end = line.find(|c: char| !c.is_digit(10)).unwrap_or(line.len());
+    if index_end == 0 {
+        return false;
+    }
+    let rest = line[index_end..].trim_start_matches(':').trim_start();
+    let rest = match rest.strip_prefix("0x") {
+        Some(rest) => rest,
+        None => return false,
+    };
+    rest.chars().next().map_or(false, |c| c.is_ascii_hexdigit())
+}
+
+/// Parse a single frame's text into an owned frame, discarding it if it does
+/// not parse as a frame for the given dialect.
+fn parse_frame(input: &str, dialect: Dialect) -> Option<OwnedFrame> {
+    let mut pairs = BacktraceParser::parse(dialect.frame_rule(), input.trim_end()).ok()?;
+    let frame = pairs.next()?;
+    debug_assert!(frame.as_rule() == dialect.frame_rule());
+
+    let mut inner = frame.into_inner();
+
+    let frame_index = inner.next().unwrap();
+    debug_assert!(frame_index.as_rule() == Rule::frame_index);
+    let index = frame_index
+        .into_span()
+        .as_str()
+        .trim_end_matches(':')
+        .trim()
+        .parse()
+        .unwrap_or(0);
+
+    // The legacy dialect carries an instruction pointer; the libstd dialect
+    // does not, so it defaults to zero when absent.
+    let address = if inner
+        .clone()
+        .next()
+        .map_or(false, |pair| pair.as_rule() == Rule::frame_pointer)
+    {
+        let frame_pointer = inner.next().unwrap();
+        let digits = frame_pointer.into_span().as_str().trim_start_matches("0x");
+        u64::from_str_radix(digits, 16).unwrap_or(0)
+    } else {
+        0
+    };
+
+    let mut symbols = Vec::new();
+    for pair in inner {
+        if pair.as_rule() != Rule::symbol_non_empty {
+            continue;
+        }
+        let mut symbol = OwnedSymbol {
+            name: None,
+            filename: None,
+            lineno: None,
+            column: None,
+        };
+        let mut inner = pair.into_inner();
+        let symbol_name = inner.next().unwrap();
+        if symbol_name.as_rule() == Rule::symbol_name_known {
+            symbol.name = Some(symbol_name.into_span().as_str().to_owned());
+        }
+        if let Some(location) = inner.next() {
+            debug_assert!(location.as_rule() == Rule::symbol_location);
+            let mut location_inner = location.into_inner();
+            let path = location_inner.next().unwrap();
+            debug_assert!(path.as_rule() == Rule::symbol_location_path);
+            symbol.filename = Some(PathBuf::from(path.into_span().as_str()));
+            let lineno = location_inner.next().unwrap();
+            debug_assert!(lineno.as_rule() == Rule::symbol_location_lineno);
+            symbol.lineno = lineno.into_span().as_str().parse().ok();
+            if let Some(column) = location_inner.next() {
+                debug_assert!(column.as_rule() == Rule::symbol_location_column);
+                symbol.column = column.into_span().as_str().parse().ok();
+            }
+        }
+        symbols.push(symbol);
+    }
+
+    Some(OwnedFrame {
+        index,
+        address,
+        symbols,
+    })
+}
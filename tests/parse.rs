@@ -1,8 +1,9 @@
 extern crate backtrace_parser;
 
+use std::io::BufReader;
 use std::path::Path;
 
-use backtrace_parser::Backtrace;
+use backtrace_parser::{Backtrace, BacktraceReader};
 
 #[test]
 fn unresolved_symbols() {
@@ -106,3 +107,113 @@ fn full_backtrace() {
 
     assert!(frames.next().is_none());
 }
+
+#[test]
+fn streaming_reader_yields_frames() {
+    let data = "stack backtrace:\n   0: 0x1234 - main\n  at src/main.rs:6\n   1: 0x0 - <no info>\n";
+    let mut reader = BacktraceReader::new(BufReader::new(data.as_bytes()));
+
+    let frame0 = reader.next_frame().unwrap().unwrap();
+    assert_eq!(frame0.index(), 0);
+    assert_eq!(frame0.address(), 0x1234);
+    assert_eq!(frame0.symbols().len(), 1);
+    assert_eq!(frame0.symbols()[0].name(), Some("main"));
+    assert_eq!(frame0.symbols()[0].filename(), Some(Path::new("src/main.rs")));
+    assert_eq!(frame0.symbols()[0].lineno(), Some(6));
+
+    let frame1 = reader.next_frame().unwrap().unwrap();
+    assert_eq!(frame1.index(), 1);
+    assert_eq!(frame1.symbols().len(), 0);
+
+    assert!(reader.next_frame().unwrap().is_none());
+}
+
+#[test]
+fn lossy_recovers_from_truncated_frame() {
+    let data = include_str!("fixtures/truncated.txt");
+    let (parsed, errors) = Backtrace::parse_lossy(data);
+
+    let frames = parsed.frames().collect::<Vec<_>>();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].symbols().collect::<Vec<_>>()[0].name(), Some("main"));
+    assert_eq!(frames[1].symbols().collect::<Vec<_>>()[0].name(), Some("foo"));
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), 3);
+    assert!(errors[0].snippet().contains("0xdead"));
+}
+
+#[test]
+fn owned_error_outlives_input() {
+    let error = {
+        let input = String::from("this is not a backtrace");
+        Backtrace::parse_owned(&input).unwrap_err()
+    };
+
+    assert_eq!(error.line(), 1);
+    assert!(error.column() >= 1);
+    assert!(!error.message().is_empty());
+    assert!(!error.expected().is_empty());
+}
+
+#[test]
+fn owned_error_matches_borrowed() {
+    let input = "this is not a backtrace";
+    let borrowed = Backtrace::parse(input).unwrap_err();
+    let owned = borrowed.to_owned();
+
+    assert_eq!(owned.offset(), 0);
+    assert_eq!(owned.line(), 1);
+}
+
+#[test]
+fn std_dialect_with_column() {
+    let data = include_str!("fixtures/std.txt");
+    let parsed = Backtrace::parse(data).unwrap();
+
+    let mut frames = parsed.frames();
+
+    let symbols0 = frames.next().unwrap().symbols().collect::<Vec<_>>();
+    assert_eq!(symbols0.len(), 1);
+    assert_eq!(symbols0[0].name(), Some("std::panicking::begin_panic"));
+    assert_eq!(
+        symbols0[0].filename(),
+        Some(Path::new("/rustc/abc123/library/std/src/panicking.rs"))
+    );
+    assert_eq!(symbols0[0].lineno(), Some(577));
+    assert_eq!(symbols0[0].column(), Some(5));
+
+    let symbols1 = frames.next().unwrap().symbols().collect::<Vec<_>>();
+    assert_eq!(symbols1[0].name(), Some("backtrace_parser::main"));
+    assert_eq!(symbols1[0].column(), Some(9));
+
+    assert!(frames.next().is_none());
+}
+
+#[test]
+fn legacy_dialect_has_no_column() {
+    let data = "stack backtrace:\n   0: 0x1234 - main\n  at src/main.rs:6\n";
+    let parsed = Backtrace::parse(data).unwrap();
+
+    let symbols = parsed.frames().next().unwrap().symbols().collect::<Vec<_>>();
+    assert_eq!(symbols[0].lineno(), Some(6));
+    assert_eq!(symbols[0].column(), None);
+}
+
+#[test]
+fn frame_index_and_address() {
+    let data = "stack backtrace:\n   0: 0x1234 - main\n   1: 0x55e06f94d05d - foo\n";
+    let parsed = Backtrace::parse(data).unwrap();
+
+    let mut frames = parsed.frames();
+
+    let frame0 = frames.next().unwrap();
+    assert_eq!(frame0.index(), 0);
+    assert_eq!(frame0.address(), 0x1234);
+
+    let frame1 = frames.next().unwrap();
+    assert_eq!(frame1.index(), 1);
+    assert_eq!(frame1.address(), 0x55e06f94d05d);
+
+    assert!(frames.next().is_none());
+}